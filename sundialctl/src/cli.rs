@@ -5,6 +5,17 @@ use clap::{Parser, Subcommand, ValueEnum};
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 pub(crate) struct Cli {
+    /// Operate on a remote host instead of the local system, e.g. `user@host`
+    #[arg(short = 'H', long, value_name = "[USER@]HOST")]
+    pub host: Option<String>,
+
+    /// Command run over `ssh` on the remote host to bridge D-Bus traffic,
+    /// for use with `--host`. Defaults to `systemd-stdio-bridge`, which is
+    /// only present on systemd hosts; non-systemd hosts need something like
+    /// `socat STDIO UNIX-CONNECT:/run/dbus/system_bus_socket`
+    #[arg(long, value_name = "COMMAND", env = "SUNDIALCTL_BRIDGE_COMMAND", default_value = "systemd-stdio-bridge")]
+    pub bridge_command: String,
+
     /// Action to perform
     #[command(subcommand)]
     pub command: Option<Commands>,
@@ -13,9 +24,14 @@ pub(crate) struct Cli {
 #[derive(Subcommand, Debug)]
 pub(crate) enum Commands {
     /// Show current time and date settings (default action)
-    Status,
+    Status {
+        /// Print in a machine-readable JSON format instead of the default
+        /// human-readable one
+        #[arg(long)]
+        json: bool,
+    },
     /// Show current time and date settings in a machine-readable format
-    // Show,
+    Show,
     /// Set the system clock
     SetTime {
         /// The new time. Various formats are supported, see sundialctl(1) for more information
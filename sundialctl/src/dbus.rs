@@ -1,5 +1,8 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use parse_datetime::parse_datetime;
+use serde::Serialize;
 use zbus::dbus_proxy;
 
 use crate::cli::{RtcMode, RtcSyncFrom};
@@ -20,8 +23,8 @@ trait timedate1 {
         interactive: bool,
     ) -> zbus::Result<()>;
 
-    // #[dbus_proxy(name = "SetNTP")]
-    // fn set_ntp(&self, use_ntp: bool, interactive: bool) -> zbus::Result<()>;
+    #[dbus_proxy(name = "SetNTP", allow_interactive_auth)]
+    fn set_ntp(&self, use_ntp: bool, interactive: bool) -> zbus::Result<()>;
 
     #[dbus_proxy(allow_interactive_auth)]
     fn set_time(&self, usec_utc: i64, relative: bool, interactive: bool) -> zbus::Result<()>;
@@ -29,14 +32,14 @@ trait timedate1 {
     #[dbus_proxy(allow_interactive_auth)]
     fn set_timezone(&self, timezone: &str, interactive: bool) -> zbus::Result<()>;
 
-    // #[dbus_proxy(property, name = "CanNTP")]
-    // fn can_ntp(&self) -> zbus::Result<bool>;
+    #[dbus_proxy(property, name = "CanNTP")]
+    fn can_ntp(&self) -> zbus::Result<bool>;
 
     #[dbus_proxy(property, name = "LocalRTC")]
     fn local_rtc(&self) -> zbus::Result<bool>;
 
-    // #[dbus_proxy(property, name = "NTP")]
-    // fn ntp(&self) -> zbus::Result<bool>;
+    #[dbus_proxy(property, name = "NTP")]
+    fn ntp(&self) -> zbus::Result<bool>;
 
     #[dbus_proxy(property, name = "NTPSynchronized")]
     fn ntpsynchronized(&self) -> zbus::Result<bool>;
@@ -51,22 +54,75 @@ trait timedate1 {
     fn timezone(&self) -> zbus::Result<String>;
 }
 
+/// machine-readable rendering of [`timedate1Proxy::status_cmd`], for the
+/// `show` subcommand and `status --json`
+#[derive(Serialize)]
+struct Status {
+    time_usec: u64,
+    timezone: String,
+    ntp_enabled: bool,
+    ntp_synchronized: bool,
+    rtctime_usec: u64,
+    local_rtc: bool,
+    local_time: String,
+    universal_time: String,
+    rtc_time: String,
+}
+
 impl timedate1Proxy<'_> {
     pub(crate) async fn status_cmd(&self, pretty: bool) -> Result<()> {
         let time = self.time_usec().await?;
-        let tz = self.timezone().await?;
+        let tz_name = self.timezone().await?;
+        let can_ntp = self.can_ntp().await?;
+        let ntp = can_ntp && self.ntp().await?;
         let ntpsync = self.ntpsynchronized().await?;
         let rtctime = self.rtctime_usec().await?;
         let localrtc = self.local_rtc().await?;
 
+        let tz: Tz = tz_name
+            .parse()
+            .map_err(|_| anyhow!("Unknown timezone: {tz_name}"))?;
+
+        let local = DateTime::<Utc>::from_timestamp_micros(time as i64)
+            .ok_or_else(|| anyhow!("System time out of range"))?
+            .with_timezone(&tz);
+        let universal = local.with_timezone(&Utc);
+        let rtc_tz = if localrtc { tz } else { Tz::UTC };
+        let rtc = DateTime::<Utc>::from_timestamp_micros(rtctime as i64)
+            .ok_or_else(|| anyhow!("RTC time out of range"))?
+            .with_timezone(&rtc_tz);
+
         if pretty {
-            println!("System time: {}", time);
-            println!("System timezone: {}", tz);
-            println!("NTP Synchronized: {}", if ntpsync { "yes" } else { "no" });
-            println!("RTC time: {}", rtctime);
-            println!("RTC timezone: {}", if localrtc { "Local" } else { "UTC" });
+            println!("Local time: {}", local.format("%a %Y-%m-%d %H:%M:%S %Z"));
+            println!(
+                "Universal time: {}",
+                universal.format("%a %Y-%m-%d %H:%M:%S UTC")
+            );
+            println!("RTC time: {}", rtc.format("%a %Y-%m-%d %H:%M:%S"));
+            println!(
+                "Time zone: {} ({}, {})",
+                tz_name,
+                local.format("%Z"),
+                local.format("%z")
+            );
+            println!(
+                "System clock synchronized: {}",
+                if ntpsync { "yes" } else { "no" }
+            );
+            println!("NTP service: {}", if ntp { "active" } else { "inactive" });
         } else {
-            todo!()
+            let status = Status {
+                time_usec: time,
+                timezone: tz_name,
+                ntp_enabled: ntp,
+                ntp_synchronized: ntpsync,
+                rtctime_usec: rtctime,
+                local_rtc: localrtc,
+                local_time: local.to_rfc3339(),
+                universal_time: universal.to_rfc3339(),
+                rtc_time: rtc.to_rfc3339(),
+            };
+            println!("{}", serde_json::to_string_pretty(&status)?);
         }
         Ok(())
     }
@@ -1,6 +1,5 @@
 use anyhow::Result;
 use clap::Parser;
-use zbus::Connection;
 
 use crate::{
     cli::{Cli, Commands},
@@ -9,18 +8,18 @@ use crate::{
 
 mod cli;
 mod dbus;
+mod transport;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    dbg!(&cli);
 
-    let conn = Connection::system().await?;
+    let conn = transport::connect(cli.host.as_deref(), &cli.bridge_command).await?;
     let proxy = timedate1Proxy::new(&conn).await?;
 
-    match cli.command.unwrap_or(Commands::Status) {
-        Commands::Status => proxy.status_cmd(true).await,
-        // Commands::Show => proxy.status_cmd(false).await,
+    match cli.command.unwrap_or(Commands::Status { json: false }) {
+        Commands::Status { json } => proxy.status_cmd(!json).await,
+        Commands::Show => proxy.status_cmd(false).await,
         Commands::SetTime {
             time,
             noninteractive,
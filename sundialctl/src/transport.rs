@@ -0,0 +1,92 @@
+//! D-Bus transport selection, mirroring timedatectl's `-H`/`--host`: operate
+//! against the local system bus by default, or tunnel to a remote one over
+//! `ssh` when a host is given.
+
+use std::pin::Pin;
+use std::process::Stdio;
+use std::task::{Context, Poll};
+
+use anyhow::{Context as _, Result};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use zbus::Connection;
+
+/// a duplex stream wrapping `ssh`'s stdin/stdout, on which the remote end is
+/// expected to speak D-Bus (via `systemd-stdio-bridge` or equivalent)
+///
+/// `ConnectionBuilder::socket` requires `S: zbus::Socket`, not bare
+/// `AsyncRead + AsyncWrite`; with zbus's `tokio` feature enabled (which this
+/// crate needs instead of the default `async-io` backend, since everything
+/// else here already runs on a `#[tokio::main]` runtime) zbus blanket-impls
+/// `Socket` for any `T: AsyncRead + AsyncWrite + Debug + Send + Sync +
+/// Unpin`, so deriving `Debug` here is what makes this satisfy that bound.
+#[derive(Debug)]
+struct SshBridge {
+    // kept alive for the lifetime of the connection; dropping it tears down
+    // the tunnel
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+}
+
+impl AsyncRead for SshBridge {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stdout).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SshBridge {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.stdin).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stdin).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stdin).poll_shutdown(cx)
+    }
+}
+
+/// connects to the local system bus, or tunnels to `host`'s system bus over
+/// `ssh` when one is given. `bridge_command` is run on the remote end to
+/// speak D-Bus over stdio; it defaults to `systemd-stdio-bridge`, but that
+/// only exists on systemd hosts, so non-systemd hosts need an equivalent
+/// passed in (e.g. `socat STDIO UNIX-CONNECT:/run/dbus/system_bus_socket`)
+pub(crate) async fn connect(host: Option<&str>, bridge_command: &str) -> Result<Connection> {
+    match host {
+        None => Connection::system().await.context("Failed to connect to system D-Bus"),
+        Some(host) => {
+            let mut child = Command::new("ssh")
+                .arg("-T")
+                .arg(host)
+                .arg(bridge_command)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .with_context(|| format!("Couldn't spawn ssh to {host}"))?;
+            let stdin = child.stdin.take().expect("piped stdin");
+            let stdout = child.stdout.take().expect("piped stdout");
+            let bridge = SshBridge {
+                _child: child,
+                stdin,
+                stdout,
+            };
+
+            zbus::ConnectionBuilder::socket(bridge)
+                .build()
+                .await
+                .with_context(|| format!("Failed to connect to D-Bus on {host}"))
+        }
+    }
+}
@@ -1,9 +1,15 @@
+use std::path::Path;
+
 use enumflags2::BitFlag;
 use nix::errno::Errno;
+use nix::sys::time::TimeSpec;
+use nix::time::{clock_gettime, clock_settime, ClockId};
 use tokio::fs::canonicalize;
-use zbus::{dbus_interface, fdo};
+use zbus::{dbus_interface, fdo, SignalContext};
 use zbus_polkit::policykit1::{AuthorityProxy, CheckAuthorizationFlags, Subject};
 
+use crate::ntp::NtpUnits;
+use crate::sntp::SntpClient;
 use crate::util::{get_hwclock, read_lines, set_hwclock, SEC_TO_USEC};
 
 const NSEC_TO_USEC: libc::c_long = 1_000;
@@ -12,58 +18,114 @@ const MAX_PHASE: libc::c_long = 500_000_000;
 pub(crate) struct TimeDate {
     pub auth: AuthorityProxy<'static>,
     pub subject: Subject,
+    pub ntp: NtpUnits,
+    pub sntp: SntpClient,
 }
 
 #[dbus_interface(name = "org.freedesktop.timedate1")]
 impl TimeDate {
     /// change the system clock
     async fn set_time(&self, usec_utc: i64, relative: bool, interactive: bool) -> fdo::Result<()> {
-        // TODO
-        // get a starting now()
+        if self.is_ntp_active().await {
+            return Err(fdo::Error::NotSupported(
+                "Cannot set the system clock while NTP is active".into(),
+            ));
+        }
+
+        // capture a starting point so we can compensate for time spent
+        // waiting on authorization below
+        let start = clock_gettime(ClockId::CLOCK_MONOTONIC)
+            .map_err(|e| fdo::Error::Failed(format!("Unable to get current time: {}", e.desc())))?;
 
-        if relative {
+        let target = if relative {
             if usec_utc == 0 {
                 return Ok(());
             }
 
-            // get now()
-            // now + usec_utc
-            // ensure no overflow/underflow
-        } else if usec_utc <= 0 {
-            return Err(fdo::Error::InvalidArgs("Invalid absolute time".into()));
-        }
+            let now = clock_gettime(ClockId::CLOCK_REALTIME)
+                .map_err(|e| fdo::Error::Failed(format!("Unable to get current time: {}", e.desc())))?;
+            let now_usec = now.tv_sec() * SEC_TO_USEC + now.tv_nsec() / NSEC_TO_USEC;
+            now_usec
+                .checked_add(usec_utc)
+                .ok_or_else(|| fdo::Error::InvalidArgs("Relative time out of range".into()))?
+        } else {
+            if usec_utc <= 0 {
+                return Err(fdo::Error::InvalidArgs("Invalid absolute time".into()));
+            }
+            usec_utc
+        };
 
         // polkit verify
         self.check_auth("org.freedesktop.timedate1.set-time", interactive)
             .await?;
-        // adjust for time spent: add now - starting timestamp
+
+        // adjust for time spent waiting on authorization
+        let waited = clock_gettime(ClockId::CLOCK_MONOTONIC)
+            .map_err(|e| fdo::Error::Failed(format!("Unable to get current time: {}", e.desc())))?
+            - start;
+        let target = target
+            .checked_add(waited.tv_sec() * SEC_TO_USEC + waited.tv_nsec() / NSEC_TO_USEC)
+            .ok_or_else(|| fdo::Error::InvalidArgs("Time out of range".into()))?;
+
         // set system clock
+        let ts = TimeSpec::new(target / SEC_TO_USEC, (target % SEC_TO_USEC) * NSEC_TO_USEC);
+        clock_settime(ClockId::CLOCK_REALTIME, ts).map_err(|e| match e {
+            Errno::EPERM => fdo::Error::AuthFailed("Not permitted to set the system clock".into()),
+            Errno::EINVAL => fdo::Error::InvalidArgs("Invalid time value".into()),
+            _ => fdo::Error::Failed(format!("Unable to set system clock: {}", e.desc())),
+        })?;
+
         // sync from sysclock to rtc
+        let local_rtc = self.local_rtc().await?;
+        set_hwclock(target, local_rtc)
+            .map_err(|e| fdo::Error::Failed(format!("Unable to sync RTC: {e}")))?;
 
         Ok(())
     }
 
     /// set the system timezone
-    async fn set_timezone(&self, timezone: String, interactive: bool) -> fdo::Result<()> {
-        // TODO
-        // check if valid tz (return if not)
-        // check if is current tz (return if true)
-        // check polkit auth
+    async fn set_timezone(
+        &self,
+        timezone: String,
+        interactive: bool,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        if !valid_timezone(&timezone).await? {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Invalid or unknown timezone: {timezone}"
+            )));
+        }
+        if timezone == self.timezone().await? {
+            return Ok(());
+        }
+
         self.check_auth("org.freedesktop.timedate1.set-timezone", interactive)
             .await?;
-        // write new localtime symlink
-        // tzset
-        // tell kernel new tz
+
+        // write new localtime symlink, atomically, the way timedated does
+        let relative_target = Path::new("..").join("usr/share/zoneinfo").join(&timezone);
+        let tmp = Path::new("/etc/.#localtime");
+        let _ = tokio::fs::remove_file(tmp).await;
+        tokio::fs::symlink(&relative_target, tmp)
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("Couldn't create temporary symlink: {e}")))?;
+        tokio::fs::rename(tmp, "/etc/localtime")
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("Couldn't replace /etc/localtime: {e}")))?;
+
+        // pick up the new zone in this process, then tell the kernel about it
+        unsafe { libc::tzset() };
+        warp_kernel_timezone()
+            .map_err(|e| fdo::Error::Failed(format!("Couldn't warp kernel timezone: {e}")))?;
+
         // if local rtc, sync rtc from sysclock
-        match self.local_rtc().await {
-            Ok(l) => {
-                if l {
-                    // TODO
-                    // set_hwclock();
-                }
-            }
-            Err(e) => todo!(),
+        if self.local_rtc().await? {
+            sync_rtc_from_system(true)
+                .map_err(|e| fdo::Error::Failed(format!("Unable to sync RTC: {e}")))?;
         }
+
+        self.timezone_changed(&ctxt).await?;
+
         Ok(())
     }
 
@@ -74,8 +136,8 @@ impl TimeDate {
         local_rtc: bool,
         fix_system: bool,
         interactive: bool,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
     ) -> fdo::Result<()> {
-        // TODO
         // if local_rtc matches current state and not fix_system, return
         let curr = self.local_rtc().await?;
         if local_rtc == curr && !fix_system {
@@ -85,24 +147,71 @@ impl TimeDate {
         // check polkit for auth
         self.check_auth("org.freedesktop.timedate1.set-local-rtc", interactive)
             .await?;
+
         // if local_rtc doesn't match, change it
         if local_rtc != curr {
-            // change value
-            todo!()
+            write_adjtime_mode(local_rtc)
+                .await
+                .map_err(|e| fdo::Error::Failed(format!("Couldn't update /etc/adjtime: {e}")))?;
         }
+
         // tell kernel the tz
+        warp_kernel_timezone()
+            .map_err(|e| fdo::Error::Failed(format!("Couldn't warp kernel timezone: {e}")))?;
+
         // sync clocks
-        // if fix_system, sync system clock from rtc
-        // else sync rtc from system clock
-        // emit prop localrtc changed
+        if fix_system {
+            // sync system clock from rtc
+            let usec = get_hwclock(local_rtc)
+                .map_err(|e| fdo::Error::Failed(format!("Couldn't read RTC: {e}")))?;
+            let usec = usec as i64;
+            let ts = TimeSpec::new(usec / SEC_TO_USEC, (usec % SEC_TO_USEC) * NSEC_TO_USEC);
+            clock_settime(ClockId::CLOCK_REALTIME, ts)
+                .map_err(|e| fdo::Error::Failed(format!("Couldn't set system clock: {}", e.desc())))?;
+        } else {
+            // sync rtc from system clock
+            sync_rtc_from_system(local_rtc)
+                .map_err(|e| fdo::Error::Failed(format!("Unable to sync RTC: {e}")))?;
+        }
+
+        self.local_rtc_changed(&ctxt).await?;
         Ok(())
     }
 
     /// control whether the system clock is synchronized with the network
     #[dbus_interface(name = "SetNTP")]
-    #[allow(unused_variables)]
-    async fn set_ntp(&self, use_ntp: bool, interactive: bool) -> fdo::Result<()> {
-        Err(fdo::Error::NotSupported("NTP is not supported".into()))
+    async fn set_ntp(
+        &self,
+        use_ntp: bool,
+        interactive: bool,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        if !self.ntp.can_ntp() && !self.sntp.can_ntp() {
+            return Err(fdo::Error::NotSupported("NTP is not supported".into()));
+        }
+        if use_ntp == self.is_ntp_active().await {
+            return Ok(());
+        }
+
+        self.check_auth("org.freedesktop.timedate1.set-ntp", interactive)
+            .await?;
+
+        // prefer a real service unit over the built-in SNTP client
+        if self.ntp.can_ntp() {
+            self.ntp
+                .set_active(use_ntp)
+                .await
+                .map_err(|e| fdo::Error::Failed(format!("Couldn't set NTP state: {e}")))?;
+        } else {
+            self.sntp
+                .set_active(use_ntp)
+                .await
+                .map_err(|e| fdo::Error::Failed(format!("Couldn't set NTP state: {e}")))?;
+        }
+
+        self.ntp_changed(&ctxt).await?;
+
+        Ok(())
     }
 
     /// returns a list of time zones known on the local system
@@ -166,19 +275,16 @@ impl TimeDate {
     }
 
     /// shows whether a service to perform time synchronization over the network is available
-    #[dbus_interface(property, name = "CanNTP")]
-    async fn can_ntp(&self) -> fdo::Result<bool> {
-        debug!("CanNTP: request received, ignoring");
-        // TODO: should this just return false?
-        Err(fdo::Error::NotSupported("NTP is not supported".into()))
+    #[dbus_interface(property(emits_changed_signal = "false"), name = "CanNTP")]
+    async fn can_ntp(&self) -> bool {
+        self.ntp.can_ntp() || self.sntp.can_ntp()
     }
 
     /// shows whether a service to perform time synchronization over the network is enabled
     #[dbus_interface(property, name = "NTP")]
-    async fn ntp(&self) -> fdo::Result<bool> {
-        debug!("NTP: request received, ignoring");
-        // TODO: should this just return false?
-        Err(fdo::Error::NotSupported("NTP is not supported".into()))
+    async fn ntp(&self) -> bool {
+        debug!("NTP: request received");
+        self.is_ntp_active().await
     }
 
     /// shows whether the kernel reports the time as synchronized
@@ -230,7 +336,8 @@ impl TimeDate {
     #[dbus_interface(property, name = "RTCTimeUSec")]
     async fn rtc_time_usec(&self) -> fdo::Result<u64> {
         debug!("RTCTimeUSec: request received");
-        match get_hwclock() {
+        let local_rtc = self.local_rtc().await?;
+        match get_hwclock(local_rtc) {
             Ok(t) => {
                 debug!("RTCTimeUSec: success: {t}");
                 Ok(t)
@@ -278,3 +385,87 @@ impl TimeDate {
         }
     }
 }
+
+impl TimeDate {
+    /// the effective `NTP` property value across both the service-unit and
+    /// built-in SNTP backends
+    async fn is_ntp_active(&self) -> bool {
+        if self.ntp.can_ntp() {
+            self.ntp.is_active().await.unwrap_or(false)
+        } else {
+            self.sntp.is_active()
+        }
+    }
+}
+
+/// rejects anything that isn't a plain relative zone name with a matching
+/// file under `/usr/share/zoneinfo`, in particular refusing path traversal
+/// attempts
+async fn valid_timezone(tz: &str) -> fdo::Result<bool> {
+    // zone.tab deliberately omits legitimate zones like `UTC`, `Etc/*`, and
+    // `GMT`, so membership there isn't a valid gate; the presence of the
+    // zoneinfo file itself is the real source of truth. `tz.is_empty()` and
+    // empty path components (leading/trailing/doubled `/`) are rejected here
+    // too, since `Path::join("")`/`Path::join("foo/")` resolve to a
+    // directory rather than a zoneinfo file.
+    if tz.is_empty() || tz.starts_with('/') || tz.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+        return Ok(false);
+    }
+
+    let path = Path::new("/usr/share/zoneinfo").join(tz);
+    Ok(tokio::fs::metadata(path).await.map(|m| m.is_file()).unwrap_or(false))
+}
+
+/// tells the kernel the current local UTC offset, the way `timedated`/
+/// `clock-util` do, so a RTC running in local time is interpreted correctly
+fn warp_kernel_timezone() -> Result<(), String> {
+    let gmtoff = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        tm.tm_gmtoff
+    };
+
+    let tz = libc::timezone {
+        tz_minuteswest: (-gmtoff / 60) as libc::c_int,
+        tz_dsttime: 0,
+    };
+
+    if unsafe { libc::settimeofday(std::ptr::null(), &tz) } < 0 {
+        return Err(Errno::last().desc().into());
+    }
+    Ok(())
+}
+
+/// rewrites the third line of `/etc/adjtime` to `UTC` or `LOCAL`, following
+/// adjtime_config(5): the drift/last-adjust lines are preserved if present
+/// and well-formed, otherwise the canonical null values are written
+async fn write_adjtime_mode(local_rtc: bool) -> std::io::Result<()> {
+    const NULL_LINE1: &str = "0.0 0 0";
+    const NULL_LINE2: &str = "0";
+
+    let mode = if local_rtc { "LOCAL" } else { "UTC" };
+    let existing = tokio::fs::read_to_string("/etc/adjtime").await.unwrap_or_default();
+    let mut lines = existing.lines();
+
+    let line1 = lines.next().filter(|l| is_valid_adjtime_line1(l)).unwrap_or(NULL_LINE1);
+    let line2 = lines.next().filter(|l| l.trim().parse::<i64>().is_ok()).unwrap_or(NULL_LINE2);
+
+    tokio::fs::write("/etc/adjtime", format!("{line1}\n{line2}\n{mode}\n")).await
+}
+
+/// validates the first line of `/etc/adjtime`: `<drift factor> <last adjust
+/// time> 0`, per adjtime_config(5)
+fn is_valid_adjtime_line1(line: &str) -> bool {
+    let mut fields = line.split_whitespace();
+    let drift = fields.next().and_then(|f| f.parse::<f64>().ok());
+    let last_adjust = fields.next().and_then(|f| f.parse::<i64>().ok());
+    let status = fields.next();
+    drift.is_some() && last_adjust.is_some() && status == Some("0") && fields.next().is_none()
+}
+
+/// writes the current system clock into the RTC
+fn sync_rtc_from_system(local_rtc: bool) -> Result<(), String> {
+    let usec = unsafe { libc::time(std::ptr::null_mut()) } * SEC_TO_USEC;
+    set_hwclock(usec, local_rtc).map_err(|e| e.to_string())
+}
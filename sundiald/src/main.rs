@@ -3,11 +3,20 @@ use zbus::Connection;
 use zbus_polkit::policykit1::{AuthorityProxy, Subject};
 
 use crate::dbus::TimeDate;
+use crate::ntp::NtpUnits;
+use crate::sntp::SntpClient;
 
 #[macro_use]
 extern crate log;
 
+/// default SNTP servers polled by the built-in client when no `ntp-units.d`
+/// service unit is available
+const DEFAULT_SNTP_SERVERS: &[&str] = &["pool.ntp.org"];
+
 mod dbus;
+mod ntp;
+mod service;
+mod sntp;
 mod util;
 
 #[tokio::main]
@@ -17,12 +26,20 @@ async fn main() -> Result<()> {
         .await
         .context("Failed to connect to system D-Bus")?;
     debug!("Connected to system D-Bus");
+
+    let ntp_units = ntp::candidate_units()
+        .await
+        .context("Failed to discover NTP service units")?;
+    debug!("Candidate NTP units: {ntp_units:?}");
+
     let timedate = TimeDate {
         auth: AuthorityProxy::new(&conn)
             .await
             .context("Failed to connect to PolicyKit")?,
         subject: Subject::new_for_owner(std::process::id(), None, None)
             .context("Failed to get PolicyKit subject")?,
+        ntp: NtpUnits::new(service::detect(conn.clone()), ntp_units),
+        sntp: SntpClient::new(DEFAULT_SNTP_SERVERS.iter().map(|s| s.to_string()).collect()),
     };
     conn.object_server()
         .at("/org/freedesktop/timedate1", timedate)
@@ -0,0 +1,95 @@
+//! Discovery of candidate NTP service units, and the glue that turns
+//! `SetNTP`/`NTP`/`CanNTP` into calls against whichever [`ServiceManager`] the
+//! daemon was started with.
+//!
+//! Candidates are read from `ntp-units.d` drop-ins the same way
+//! `timedated` does: one unit name per line in `*.list` files, with a file
+//! in `/etc/systemd/ntp-units.d` overriding a same-named file shipped in
+//! `/usr/lib/systemd/ntp-units.d`.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+
+use crate::service::ServiceManager;
+use crate::util::read_lines;
+
+const NTP_UNITS_DIRS: &[&str] = &["/usr/lib/systemd/ntp-units.d", "/etc/systemd/ntp-units.d"];
+
+/// Returns the candidate NTP service units, in discovery order, with later
+/// directories' files overriding earlier ones of the same name.
+pub(crate) async fn candidate_units() -> Result<Vec<String>> {
+    let mut files: BTreeMap<String, std::path::PathBuf> = BTreeMap::new();
+
+    for dir in NTP_UNITS_DIRS {
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).context(format!("Couldn't read {dir}")),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "list") {
+                if let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) {
+                    files.insert(name, path);
+                }
+            }
+        }
+    }
+
+    let mut units = Vec::new();
+    for path in files.into_values() {
+        for line in read_lines(&path).await.context("Couldn't read ntp-units.d list")?.flatten() {
+            let unit = line.trim();
+            if !unit.is_empty() && !unit.starts_with('#') && !units.iter().any(|u| u == unit) {
+                units.push(unit.to_string());
+            }
+        }
+    }
+
+    Ok(units)
+}
+
+/// Picks the first candidate unit and manages it through a [`ServiceManager`].
+pub(crate) struct NtpUnits {
+    manager: Box<dyn ServiceManager>,
+    units: Vec<String>,
+}
+
+impl NtpUnits {
+    pub(crate) fn new(manager: Box<dyn ServiceManager>, units: Vec<String>) -> Self {
+        Self { manager, units }
+    }
+
+    /// Whether any candidate NTP service unit was found on this system.
+    pub(crate) fn can_ntp(&self) -> bool {
+        !self.units.is_empty()
+    }
+
+    fn first_unit(&self) -> Option<&str> {
+        self.units.first().map(String::as_str)
+    }
+
+    /// Whether the first candidate unit is currently active.
+    pub(crate) async fn is_active(&self) -> Result<bool> {
+        match self.first_unit() {
+            Some(unit) => self.manager.is_active(unit).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Enables and starts (or stops and disables) the first candidate unit.
+    pub(crate) async fn set_active(&self, enable: bool) -> Result<()> {
+        let unit = self
+            .first_unit()
+            .context("No NTP service unit is available")?;
+        if enable {
+            self.manager.enable(unit).await?;
+            self.manager.start(unit).await?;
+        } else {
+            self.manager.stop(unit).await?;
+            self.manager.disable(unit).await?;
+        }
+        Ok(())
+    }
+}
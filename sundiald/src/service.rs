@@ -0,0 +1,191 @@
+//! Abstraction over the local init system, so the NTP subsystem can enable,
+//! disable, start, and stop a service unit without caring whether the host
+//! runs systemd, runit, or something else entirely.
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use zbus::{dbus_proxy, zvariant::OwnedObjectPath, Connection};
+
+/// Picks a [`ServiceManager`] backend at runtime: systemd when
+/// `/run/systemd/system` exists, runit otherwise.
+pub(crate) fn detect(conn: Connection) -> Box<dyn ServiceManager> {
+    if std::path::Path::new("/run/systemd/system").exists() {
+        Box::new(SystemdServiceManager::new(conn))
+    } else {
+        Box::new(RunitServiceManager::new())
+    }
+}
+
+/// The set of operations needed to manage a single service unit, regardless
+/// of which init system actually owns it.
+#[async_trait]
+pub(crate) trait ServiceManager: Send + Sync {
+    async fn is_active(&self, unit: &str) -> Result<bool>;
+    async fn enable(&self, unit: &str) -> Result<()>;
+    async fn disable(&self, unit: &str) -> Result<()>;
+    async fn start(&self, unit: &str) -> Result<()>;
+    async fn stop(&self, unit: &str) -> Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Systemd1Manager {
+    #[dbus_proxy(name = "StartUnit")]
+    fn start_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[dbus_proxy(name = "StopUnit")]
+    fn stop_unit(&self, name: &str, mode: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[dbus_proxy(name = "EnableUnitFiles")]
+    fn enable_unit_files(
+        &self,
+        files: &[&str],
+        runtime: bool,
+        force: bool,
+    ) -> zbus::Result<(bool, Vec<(String, String, String)>)>;
+
+    #[dbus_proxy(name = "DisableUnitFiles")]
+    fn disable_unit_files(
+        &self,
+        files: &[&str],
+        runtime: bool,
+    ) -> zbus::Result<Vec<(String, String, String)>>;
+
+    #[dbus_proxy(name = "GetUnit")]
+    fn get_unit(&self, name: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.systemd1.Unit",
+    default_service = "org.freedesktop.systemd1"
+)]
+trait Systemd1Unit {
+    #[dbus_proxy(property)]
+    fn active_state(&self) -> zbus::Result<String>;
+}
+
+/// Manages units through systemd's D-Bus API (`org.freedesktop.systemd1`).
+pub(crate) struct SystemdServiceManager {
+    conn: Connection,
+}
+
+impl SystemdServiceManager {
+    pub(crate) fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    async fn manager(&self) -> Result<Systemd1ManagerProxy<'_>> {
+        Ok(Systemd1ManagerProxy::new(&self.conn).await?)
+    }
+}
+
+#[async_trait]
+impl ServiceManager for SystemdServiceManager {
+    async fn is_active(&self, unit: &str) -> Result<bool> {
+        let path = self.manager().await?.get_unit(unit).await?;
+        let unit_proxy = Systemd1UnitProxy::builder(&self.conn)
+            .path(path)?
+            .build()
+            .await?;
+        Ok(unit_proxy.active_state().await? == "active")
+    }
+
+    async fn enable(&self, unit: &str) -> Result<()> {
+        self.manager()
+            .await?
+            .enable_unit_files(&[unit], false, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn disable(&self, unit: &str) -> Result<()> {
+        self.manager().await?.disable_unit_files(&[unit], false).await?;
+        Ok(())
+    }
+
+    async fn start(&self, unit: &str) -> Result<()> {
+        self.manager().await?.start_unit(unit, "replace").await?;
+        Ok(())
+    }
+
+    async fn stop(&self, unit: &str) -> Result<()> {
+        self.manager().await?.stop_unit(unit, "replace").await?;
+        Ok(())
+    }
+}
+
+/// Manages services on runit-based systems by symlinking into the active
+/// service directory and shelling out to `sv`(8), the way `runit-init`
+/// systems expect services to be toggled.
+pub(crate) struct RunitServiceManager {
+    service_dir: std::path::PathBuf,
+    available_dir: std::path::PathBuf,
+}
+
+impl RunitServiceManager {
+    pub(crate) fn new() -> Self {
+        Self {
+            service_dir: "/var/service".into(),
+            available_dir: "/etc/sv".into(),
+        }
+    }
+
+    fn link_path(&self, unit: &str) -> std::path::PathBuf {
+        self.service_dir.join(unit)
+    }
+}
+
+#[async_trait]
+impl ServiceManager for RunitServiceManager {
+    async fn is_active(&self, unit: &str) -> Result<bool> {
+        let output = tokio::process::Command::new("sv")
+            .arg("status")
+            .arg(self.link_path(unit))
+            .output()
+            .await?;
+        Ok(output.status.success() && String::from_utf8_lossy(&output.stdout).starts_with("run:"))
+    }
+
+    async fn enable(&self, unit: &str) -> Result<()> {
+        let link = self.link_path(unit);
+        if tokio::fs::symlink_metadata(&link).await.is_err() {
+            tokio::fs::symlink(self.available_dir.join(unit), link).await?;
+        }
+        Ok(())
+    }
+
+    async fn disable(&self, unit: &str) -> Result<()> {
+        let link = self.link_path(unit);
+        if tokio::fs::symlink_metadata(&link).await.is_ok() {
+            tokio::fs::remove_file(link).await?;
+        }
+        Ok(())
+    }
+
+    async fn start(&self, unit: &str) -> Result<()> {
+        let status = tokio::process::Command::new("sv")
+            .arg("up")
+            .arg(self.link_path(unit))
+            .status()
+            .await?;
+        if !status.success() {
+            bail!("sv up {unit} failed: {status}");
+        }
+        Ok(())
+    }
+
+    async fn stop(&self, unit: &str) -> Result<()> {
+        let status = tokio::process::Command::new("sv")
+            .arg("down")
+            .arg(self.link_path(unit))
+            .status()
+            .await?;
+        if !status.success() {
+            bail!("sv down {unit} failed: {status}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+//! A minimal built-in SNTP (RFC 4330) client, so `CanNTP`/`NTP` can still be
+//! meaningful on systems with no separate time-sync daemon or service unit
+//! installed. When enabled, a background task periodically polls the
+//! configured servers and steps or slews the clock towards their reported
+//! time, but only while the host appears to have network connectivity.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use nix::sys::time::TimeSpec;
+use nix::time::{clock_gettime, clock_settime, ClockId};
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+const NTP_PORT: u16 = 123;
+/// seconds between the NTP epoch (1900-01-01) and the Unix epoch
+const NTP_EPOCH_OFFSET: i64 = 2_208_988_800;
+const POLL_INTERVAL: Duration = Duration::from_secs(64);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+/// offsets smaller than this are slewed via `adjtimex`; larger ones are
+/// stepped directly with `clock_settime`
+const SLEW_THRESHOLD_USEC: i64 = 128_000;
+
+struct SntpResult {
+    offset_usec: i64,
+    delay_usec: i64,
+}
+
+/// returns the current time as a 64-bit NTP (Q32.32) timestamp
+fn now_ntp() -> u64 {
+    let ts = clock_gettime(ClockId::CLOCK_REALTIME).unwrap_or(TimeSpec::new(0, 0));
+    let secs = (ts.tv_sec() + NTP_EPOCH_OFFSET) as u64;
+    let frac = ((ts.tv_nsec() as u64) << 32) / 1_000_000_000;
+    (secs << 32) | frac
+}
+
+/// the difference `a - b` between two NTP timestamps, in microseconds
+fn diff_usec(a: u64, b: u64) -> i64 {
+    let diff = (a as i64).wrapping_sub(b as i64);
+    ((diff as i128 * 1_000_000) >> 32) as i64
+}
+
+/// sends a single SNTP client request to `server` and computes the clock
+/// offset and round-trip delay per RFC 4330 section 5
+async fn query(server: &str) -> Result<SntpResult> {
+    let addr = tokio::net::lookup_host((server, NTP_PORT))
+        .await
+        .with_context(|| format!("Couldn't resolve {server}"))?
+        .next()
+        .with_context(|| format!("No addresses for {server}"))?;
+
+    let sock = UdpSocket::bind("0.0.0.0:0").await?;
+    sock.connect(addr).await?;
+
+    let mut req = [0u8; 48];
+    req[0] = 0b00_100_011; // LI = 0 (no warning), VN = 4, Mode = 3 (client)
+    let t1 = now_ntp();
+    req[40..48].copy_from_slice(&t1.to_be_bytes());
+    sock.send(&req).await?;
+
+    let mut reply = [0u8; 48];
+    let n = tokio::time::timeout(REPLY_TIMEOUT, sock.recv(&mut reply))
+        .await
+        .with_context(|| format!("Timed out waiting for {server}"))??;
+    let t4 = now_ntp();
+    if n < reply.len() {
+        bail!("Short SNTP reply from {server}");
+    }
+
+    let leap_indicator = reply[0] >> 6;
+    let stratum = reply[1];
+    if stratum == 0 {
+        bail!("Kiss-of-Death reply from {server}");
+    }
+    if leap_indicator == 3 {
+        bail!("{server} reports itself as unsynchronized");
+    }
+
+    let orig = u64::from_be_bytes(reply[24..32].try_into().unwrap());
+    if orig != t1 {
+        bail!("Mismatched originate timestamp from {server}");
+    }
+    let t2 = u64::from_be_bytes(reply[32..40].try_into().unwrap());
+    let t3 = u64::from_be_bytes(reply[40..48].try_into().unwrap());
+
+    Ok(SntpResult {
+        offset_usec: (diff_usec(t2, t1) + diff_usec(t3, t4)) / 2,
+        delay_usec: diff_usec(t4, t1) - diff_usec(t3, t2),
+    })
+}
+
+/// a crude connectivity probe: if we can't even get a route to a public
+/// resolver, don't bother sending SNTP requests
+async fn network_available() -> bool {
+    match UdpSocket::bind("0.0.0.0:0").await {
+        Ok(sock) => sock.connect(("1.1.1.1", 53)).await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// slews small offsets via the kernel PLL (`adjtimex`), and steps the clock
+/// directly for anything larger
+fn apply_offset(offset_usec: i64) -> Result<()> {
+    if offset_usec.abs() < SLEW_THRESHOLD_USEC {
+        let mut tx: libc::timex = unsafe { std::mem::zeroed() };
+        tx.modes = (libc::ADJ_OFFSET | libc::ADJ_STATUS) as u32;
+        tx.offset = offset_usec as libc::c_long;
+        tx.status = libc::STA_PLL;
+        if unsafe { libc::adjtimex(&mut tx) } < 0 {
+            bail!("adjtimex: {}", nix::errno::Errno::last());
+        }
+    } else {
+        let now = clock_gettime(ClockId::CLOCK_REALTIME)?;
+        let now_usec = now.tv_sec() * 1_000_000 + now.tv_nsec() / 1_000;
+        let target = now_usec + offset_usec;
+        let ts = TimeSpec::new(target / 1_000_000, (target % 1_000_000) * 1_000);
+        clock_settime(ClockId::CLOCK_REALTIME, ts)?;
+    }
+    Ok(())
+}
+
+/// the background time-sync task driven by `SetNTP(true)`
+pub(crate) struct SntpClient {
+    servers: Vec<String>,
+    active: Arc<AtomicBool>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl SntpClient {
+    pub(crate) fn new(servers: Vec<String>) -> Self {
+        Self {
+            servers,
+            active: Arc::new(AtomicBool::new(false)),
+            task: Mutex::new(None),
+        }
+    }
+
+    /// whether any SNTP servers are configured at all
+    pub(crate) fn can_ntp(&self) -> bool {
+        !self.servers.is_empty()
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    pub(crate) async fn set_active(&self, enable: bool) -> Result<()> {
+        let mut task = self.task.lock().await;
+        if enable {
+            if task.is_some() {
+                return Ok(());
+            }
+            self.active.store(true, Ordering::Relaxed);
+            let servers = self.servers.clone();
+            let active = self.active.clone();
+            *task = Some(tokio::spawn(Self::run(servers, active)));
+        } else {
+            self.active.store(false, Ordering::Relaxed);
+            if let Some(handle) = task.take() {
+                handle.abort();
+            }
+        }
+        Ok(())
+    }
+
+    async fn run(servers: Vec<String>, active: Arc<AtomicBool>) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        while active.load(Ordering::Relaxed) {
+            interval.tick().await;
+            if !active.load(Ordering::Relaxed) {
+                break;
+            }
+            if !network_available().await {
+                debug!("sntp: no network connectivity, skipping this round");
+                continue;
+            }
+            for server in &servers {
+                match query(server).await {
+                    Ok(result) => {
+                        debug!(
+                            "sntp: {server}: offset={}us delay={}us",
+                            result.offset_usec, result.delay_usec
+                        );
+                        if let Err(e) = apply_offset(result.offset_usec) {
+                            warn!("sntp: failed to apply offset from {server}: {e}");
+                        }
+                        break;
+                    }
+                    Err(e) => debug!("sntp: {server}: {e}"),
+                }
+            }
+        }
+    }
+}
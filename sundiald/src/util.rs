@@ -68,66 +68,98 @@ const RTC_SET_TIME_ID: u8 = 0x0a;
 ioctl_read!(rtc_read_time, RTC_MAGIC, RTC_RD_TIME_ID, RtcTime);
 ioctl_read!(rtc_set_time, RTC_MAGIC, RTC_SET_TIME_ID, RtcTime);
 
-fn rtc_open() -> nix::Result<RawFd> {
-    nix::fcntl::open(
-        "/dev/rtc",
-        OFlag::O_RDONLY | OFlag::O_CLOEXEC,
-        Mode::empty(),
-    )
+/// candidate RTC device nodes, tried in order, since not every system
+/// exposes the RTC at the conventional `/dev/rtc` path
+const RTC_DEVICES: &[&str] = &["/dev/rtc", "/dev/rtc0", "/dev/misc/rtc"];
+
+/// an error accessing the RTC device, distinguishing "another process has
+/// it open" (`EBUSY`) from other failures so callers can report it clearly
+#[derive(Debug)]
+pub(crate) enum RtcError {
+    Busy,
+    Io(Errno),
+}
+
+impl std::fmt::Display for RtcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Busy => write!(f, "RTC is busy"),
+            Self::Io(e) => write!(f, "{}", e.desc()),
+        }
+    }
+}
+
+impl std::error::Error for RtcError {}
+
+/// opens the first available RTC device, following an `EBUSY` reply as a
+/// definitive "someone else is using it" rather than continuing to probe
+fn rtc_open() -> Result<RawFd, RtcError> {
+    let mut last_err = Errno::ENOENT;
+    for dev in RTC_DEVICES {
+        match nix::fcntl::open(*dev, OFlag::O_RDONLY | OFlag::O_CLOEXEC, Mode::empty()) {
+            Ok(fd) => return Ok(fd),
+            Err(Errno::EBUSY) => return Err(RtcError::Busy),
+            Err(e) => last_err = e,
+        }
+    }
+    Err(RtcError::Io(last_err))
 }
 
-fn rtc_close(fd: RawFd) -> nix::Result<()> {
-    nix::unistd::close(fd)
+fn rtc_close(fd: RawFd) -> Result<(), RtcError> {
+    nix::unistd::close(fd).map_err(RtcError::Io)
 }
 
-fn rtc_read(fd: RawFd) -> nix::Result<RtcTime> {
+fn rtc_read(fd: RawFd) -> Result<RtcTime, RtcError> {
     let mut buf: RtcTime = unsafe { std::mem::zeroed() };
     match unsafe { rtc_read_time(fd, &mut buf) } {
         Ok(_) => Ok(buf),
-        Err(e) => Err(e),
+        Err(e) => Err(RtcError::Io(e)),
     }
 }
 
-fn rtc_write(fd: RawFd, tm: impl Into<RtcTime>) -> nix::Result<()> {
+fn rtc_write(fd: RawFd, tm: impl Into<RtcTime>) -> Result<(), RtcError> {
     let mut buf: RtcTime = tm.into();
     match unsafe { rtc_set_time(fd, &mut buf) } {
         Ok(_) => Ok(()),
-        Err(e) => Err(e),
+        Err(e) => Err(RtcError::Io(e)),
     }
 }
 
-pub(crate) fn get_hwclock() -> nix::Result<u64> {
-    match rtc_open() {
-        Ok(fd) => {
-            let ret = match rtc_read(fd) {
-                Ok(tm) => Ok((unsafe { libc::timegm(&mut tm.into()) } * SEC_TO_USEC)
-                    .try_into()
-                    .unwrap_or_default()),
-                Err(e) => Err(e),
-            };
-            if let Err(e) = rtc_close(fd) {
-                return Err(e);
-            }
-            ret
-        }
-        Err(e) => Err(e),
-    }
+/// reads the RTC and returns the current time in µs since the Unix epoch.
+/// `local_rtc` selects whether the broken-down time read back is
+/// interpreted as local time (`mktime`) or UTC (`timegm`), matching the
+/// `LocalRTC` property.
+pub(crate) fn get_hwclock(local_rtc: bool) -> Result<u64, RtcError> {
+    let fd = rtc_open()?;
+    let ret = rtc_read(fd).map(|tm| {
+        let mut tm: libc::tm = tm.into();
+        let secs = if local_rtc {
+            unsafe { libc::mktime(&mut tm) }
+        } else {
+            unsafe { libc::timegm(&mut tm) }
+        };
+        (secs * SEC_TO_USEC).try_into().unwrap_or_default()
+    });
+    rtc_close(fd)?;
+    ret
 }
 
-pub(crate) fn set_hwclock(tm: impl Into<RtcTime>) -> nix::Result<()> {
-    match rtc_open() {
-        Ok(fd) => {
-            let ret = match rtc_write(fd, tm) {
-                Ok(_) => Ok(()),
-                Err(e) => Err(e),
-            };
-            if let Err(e) = rtc_close(fd) {
-                return Err(e);
-            }
-            ret
-        }
-        Err(e) => Err(e),
+/// writes `usec` (µs since the Unix epoch) to the RTC. `local_rtc` selects
+/// whether the broken-down time written is produced via `localtime_r`
+/// (local time) or `gmtime_r` (UTC), matching the `LocalRTC` property.
+pub(crate) fn set_hwclock(usec: i64, local_rtc: bool) -> Result<(), RtcError> {
+    let secs = usec / SEC_TO_USEC;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if local_rtc {
+        unsafe { libc::localtime_r(&secs, &mut tm) };
+    } else {
+        unsafe { libc::gmtime_r(&secs, &mut tm) };
     }
+
+    let fd = rtc_open()?;
+    let ret = rtc_write(fd, tm);
+    rtc_close(fd)?;
+    ret
 }
 
 pub(crate) async fn read_lines<P: AsRef<Path>>(